@@ -0,0 +1,154 @@
+/**
+ * Prime and pairwise-coprime modulus generation for building CRT systems.
+ */
+pub mod primes {
+
+    use num_bigint::{BigInt, Sign};
+    use rand::RngCore;
+
+    use crate::cnrt::cnrt::bezout;
+
+    /** Draw a uniform random value in `[0, bound)`. */
+    fn random_below<R: RngCore>(bound: &BigInt, rng: &mut R) -> BigInt {
+	let bytes = ((bound.bits() + 7) / 8).max(1) as usize;
+	loop {
+	    let mut buf = vec![0u8; bytes];
+	    rng.fill_bytes(&mut buf);
+	    let candidate = BigInt::from_bytes_be(Sign::Plus, &buf);
+	    if candidate < *bound {
+		return candidate;
+	    }
+	}
+    }
+
+    /** Miller-Rabin primality test: write `n - 1 = 2^s * d` with `d` odd,
+     * then for `rounds` random bases `a` in `[2, n-2]` check that
+     * `a^d mod n == 1` or `a^(2^r * d) mod n == n-1` for some
+     * `0 <= r < s`, declaring `n` composite the first time neither holds.
+     * A `true` result is "probably prime"; the false-positive chance
+     * shrinks by roughly `4^-rounds`. */
+    pub fn is_probable_prime(n: &BigInt, rounds: usize) -> bool {
+	let zero = BigInt::from(0);
+	let one = BigInt::from(1);
+	let two = BigInt::from(2);
+	let three = BigInt::from(3);
+
+	if *n < two {
+	    return false;
+	}
+	if *n == two || *n == three {
+	    return true;
+	}
+	if n % &two == zero {
+	    return false;
+	}
+
+	let n_minus_one = n - &one;
+	let mut d = n_minus_one.clone();
+	let mut s: u32 = 0;
+	while &d % &two == zero {
+	    d /= &two;
+	    s += 1;
+	}
+
+	let mut rng = rand::thread_rng();
+	'witness: for _ in 0..rounds {
+	    let a = &two + random_below(&(n - &three), &mut rng); // a in [2, n-2]
+	    let mut x = a.modpow(&d, n);
+	    if x == one || x == n_minus_one {
+		continue;
+	    }
+	    for _ in 1..s {
+		x = x.modpow(&two, n);
+		if x == n_minus_one {
+		    continue 'witness;
+		}
+	    }
+	    return false;
+	}
+	true
+    }
+
+    /** Generate a random `bits`-bit probable prime, drawing candidates
+     * from `rng` until `is_probable_prime` accepts one. */
+    pub fn generate_prime<R: RngCore>(bits: usize, rng: &mut R) -> BigInt {
+	assert!(bits >= 2, "generate_prime requires at least 2 bits");
+	let byte_len = (bits + 7) / 8;
+	let excess_bits = byte_len * 8 - bits; // padding bits in the top byte to clear
+	loop {
+	    let mut buf = vec![0u8; byte_len];
+	    rng.fill_bytes(&mut buf);
+	    if let Some(top) = buf.first_mut() {
+		*top &= 0xFFu8 >> excess_bits; // clear the padding above bit (bits-1)
+		*top |= 1 << (7 - excess_bits); // force the requested bit length
+	    }
+	    if let Some(bottom) = buf.last_mut() {
+		*bottom |= 0x01; // force odd
+	    }
+	    let candidate = BigInt::from_bytes_be(Sign::Plus, &buf);
+	    if is_probable_prime(&candidate, 40) {
+		return candidate;
+	    }
+	}
+    }
+
+    /** Generate `count` pairwise-coprime `bits`-bit prime moduli, suitable
+     * for feeding directly into `RemainderValue::merge` or
+     * `RemainderValue::reconstruct_garner`. */
+    pub fn generate_coprime_moduli<R: RngCore>(count: usize, bits: usize, rng: &mut R) -> Vec<BigInt> {
+	let mut moduli: Vec<BigInt> = Vec::with_capacity(count);
+	while moduli.len() < count {
+	    let candidate = generate_prime(bits, rng);
+	    let coprime_with_all = moduli.iter().all(|m| bezout(m, &candidate).2 == BigInt::from(1));
+	    if coprime_with_all {
+		moduli.push(candidate);
+	    }
+	}
+	moduli
+    }
+
+    #[cfg(test)]
+    mod tests {
+	use super::*;
+	use rand::{rngs::StdRng, SeedableRng};
+
+	#[test]
+	fn is_probable_prime_accepts_small_primes() {
+	    for p in [2, 3, 5, 7, 11, 97, 7919] {
+		assert!(is_probable_prime(&BigInt::from(p), 40), "{p} should be prime");
+	    }
+	}
+
+	#[test]
+	fn is_probable_prime_rejects_small_composites_and_edge_cases() {
+	    for n in [-1, 0, 1, 4, 9, 100, 561] { // 561 is the smallest Carmichael number
+		assert!(!is_probable_prime(&BigInt::from(n), 40), "{n} should not be prime");
+	    }
+	}
+
+	#[test]
+	fn generate_prime_has_the_exact_requested_bit_length() {
+	    let mut rng = StdRng::seed_from_u64(42);
+	    for bits in [8, 10, 16, 17, 24, 30, 32] {
+		let p = generate_prime(bits, &mut rng);
+		assert_eq!(p.bits() as usize, bits, "generate_prime({bits}, ..) returned {} bits", p.bits());
+		assert!(is_probable_prime(&p, 40));
+	    }
+	}
+
+	#[test]
+	fn generate_coprime_moduli_are_pairwise_coprime_primes() {
+	    let mut rng = StdRng::seed_from_u64(7);
+	    let moduli = generate_coprime_moduli(4, 16, &mut rng);
+	    assert_eq!(moduli.len(), 4);
+	    for m in &moduli {
+		assert!(is_probable_prime(m, 40));
+	    }
+	    for i in 0..moduli.len() {
+		for j in (i + 1)..moduli.len() {
+		    assert_eq!(bezout(&moduli[i], &moduli[j]).2, BigInt::from(1));
+		}
+	    }
+	}
+    }
+}